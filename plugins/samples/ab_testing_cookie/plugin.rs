@@ -0,0 +1,206 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// [START serviceextensions_plugin_ab_testing_cookie]
+use json_config::load_json_config;
+use log::*;
+use proxy_wasm::traits::*;
+use proxy_wasm::types::*;
+use serde::Deserialize;
+use std::rc::Rc;
+use uuid::Uuid;
+
+// Name of both the sticky cookie and the request header sent upstream.
+const BUCKET_NAME: &str = "x-exp-bucket";
+const COOKIE_MAX_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+proxy_wasm::main! {{
+    proxy_wasm::set_log_level(LogLevel::Trace);
+    proxy_wasm::set_root_context(|_| -> Box<dyn RootContext> {
+        Box::new(MyRootContext {
+            config: Rc::new(ExperimentConfig::default()),
+        })
+    });
+}}
+
+#[derive(Deserialize, Debug, Default)]
+struct ExperimentConfig {
+    buckets: Vec<Bucket>,
+    // Optional request header holding a stable user identifier. When absent
+    // (or the header is missing), new users are assigned at random.
+    #[serde(default)]
+    id_header: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Bucket {
+    name: String,
+    weight: u64,
+}
+
+impl ExperimentConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.buckets.is_empty() {
+            return Err("At least one bucket must be configured".to_string());
+        }
+        for bucket in self.buckets.iter() {
+            let valid_name = !bucket.name.is_empty()
+                && bucket
+                    .name
+                    .bytes()
+                    .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_');
+            if !valid_name {
+                return Err(format!("Invalid bucket name: {:?}", bucket.name));
+            }
+        }
+        if self.total_weight() == 0 {
+            return Err("Bucket weights must not all be zero".to_string());
+        }
+        Ok(())
+    }
+
+    fn total_weight(&self) -> u64 {
+        self.buckets.iter().map(|b| b.weight).sum()
+    }
+
+    fn find_bucket(&self, name: &str) -> Option<&Bucket> {
+        self.buckets.iter().find(|b| b.name == name)
+    }
+
+    // Maps a hash onto a bucket in proportion to the configured weights.
+    fn pick_bucket(&self, hash: u64) -> &Bucket {
+        let mut point = hash % self.total_weight();
+        for bucket in self.buckets.iter() {
+            if point < bucket.weight {
+                return bucket;
+            }
+            point -= bucket.weight;
+        }
+        unreachable!("point is always below the total weight")
+    }
+}
+
+struct MyRootContext {
+    config: Rc<ExperimentConfig>,
+}
+
+impl Context for MyRootContext {}
+
+impl RootContext for MyRootContext {
+    fn on_configure(&mut self, _: usize) -> bool {
+        // Config file contains JSON formatted experiment config.
+        let config = match load_json_config::<ExperimentConfig>(self.get_plugin_configuration()) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("{}", e);
+                return false;
+            }
+        };
+        if let Err(e) = config.validate() {
+            error!("{}", e);
+            return false;
+        }
+        info!("Loaded {} experiment buckets", config.buckets.len());
+        self.config = Rc::new(config);
+        return true;
+    }
+
+    fn create_http_context(&self, _: u32) -> Option<Box<dyn HttpContext>> {
+        Some(Box::new(MyHttpContext {
+            config: self.config.clone(),
+            new_bucket: None,
+        }))
+    }
+
+    fn get_type(&self) -> Option<ContextType> {
+        Some(ContextType::HttpContext)
+    }
+}
+
+// 64-bit FNV-1a, used instead of DefaultHasher so that assignments are stable
+// across plugin builds and Rust versions.
+fn fnv1a(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in data {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+struct MyHttpContext {
+    config: Rc<ExperimentConfig>,
+    // Bucket assigned during this request, to be persisted in a cookie.
+    new_bucket: Option<String>,
+}
+
+impl MyHttpContext {
+    // Returns the bucket named by the request's cookie, if it is still
+    // configured.
+    fn bucket_from_cookie(&self) -> Option<String> {
+        let cookies = self.get_http_request_header("Cookie")?;
+        cookies
+            .split(';')
+            .filter_map(|pair| pair.trim().split_once('='))
+            .find(|(name, _)| *name == BUCKET_NAME)
+            .and_then(|(_, value)| self.config.find_bucket(value))
+            .map(|bucket| bucket.name.clone())
+    }
+
+    fn assign_bucket(&self) -> String {
+        let stable_id = match &self.config.id_header {
+            Some(header) => self.get_http_request_header(header),
+            None => None,
+        };
+        let hash = match stable_id {
+            Some(id) if !id.is_empty() => fnv1a(id.as_bytes()),
+            _ => Uuid::new_v4().as_u64_pair().0,
+        };
+        self.config.pick_bucket(hash).name.clone()
+    }
+}
+
+impl Context for MyHttpContext {}
+
+impl HttpContext for MyHttpContext {
+    fn on_http_request_headers(&mut self, _: usize, _: bool) -> Action {
+        let bucket = match self.bucket_from_cookie() {
+            Some(bucket) => {
+                debug!("Returning user in bucket {}", bucket);
+                bucket
+            }
+            None => {
+                let bucket = self.assign_bucket();
+                info!("Assigned new user to bucket {}", bucket);
+                self.new_bucket = Some(bucket.clone());
+                bucket
+            }
+        };
+        // Overwrites any client-supplied value so upstream routing can trust it.
+        self.set_http_request_header(BUCKET_NAME, Some(&bucket));
+        return Action::Continue;
+    }
+
+    fn on_http_response_headers(&mut self, _: usize, _: bool) -> Action {
+        if let Some(bucket) = &self.new_bucket {
+            let cookie = format!(
+                "{}={}; Path=/; Max-Age={}",
+                BUCKET_NAME, bucket, COOKIE_MAX_AGE_SECS
+            );
+            self.add_http_response_header("Set-Cookie", &cookie);
+        }
+        return Action::Continue;
+    }
+}
+// [END serviceextensions_plugin_ab_testing_cookie]
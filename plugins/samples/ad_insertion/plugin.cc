@@ -21,9 +21,14 @@
 #include <vector>
 
 #include "absl/strings/ascii.h"
+#include "absl/strings/numbers.h"
 #include "absl/strings/str_split.h"
 #include "proxy_wasm_intrinsics.h"
 
+// Default limit on the size of HTML bodies that are rewritten. Larger bodies
+// are passed through untouched to bound memory usage.
+constexpr size_t kMaxHtmlBytes = 1024 * 1024;
+
 class MyRootContext : public RootContext {
  public:
   explicit MyRootContext(uint32_t id, std::string_view root_id)
@@ -33,6 +38,7 @@ class MyRootContext : public RootContext {
     // 1. Set default configurations for fallback and testing purposes.
     gpt_library_url_ = "https://securepubads.g.doubleclick.net/tag/js/gpt.js";
     inject_gpt_library_ = true;
+    max_html_bytes_ = kMaxHtmlBytes;
     ad_configs_ = {
         {"header", {"/1234/header_ad", "728x90", "<body>", false}},
         {"content", {"/1234/content_ad", "300x250", "<article>", false}},
@@ -59,6 +65,7 @@ class MyRootContext : public RootContext {
     // Expected format per line (comma-separated):
     // gpt_url, <url>
     // inject_gpt, <true|false>
+    // max_html_bytes, <bytes>
     // ad, <position>, <gam_slot>, <size>, <insert_before_bool>, <marker>
     for (absl::string_view line : absl::StrSplit(config_str, '\n')) {
       absl::string_view stripped = absl::StripAsciiWhitespace(line);
@@ -76,7 +83,17 @@ class MyRootContext : public RootContext {
         gpt_library_url_ = std::string(parts[1]);
       } else if (parts[0] == "inject_gpt" && parts.size() >= 2) {
         inject_gpt_library_ = (parts[1] == "true");
+      } else if (parts[0] == "max_html_bytes" && parts.size() >= 2) {
+        if (!absl::SimpleAtoi(parts[1], &max_html_bytes_)) {
+          LOG_WARN("Invalid configuration line: " + std::string(stripped));
+          max_html_bytes_ = kMaxHtmlBytes;
+        }
       } else if (parts[0] == "ad" && parts.size() >= 6) {
+        // Markers must be non-empty and sizes must be "WxH".
+        if (parts[5].empty() || !isValidSize(parts[3])) {
+          LOG_WARN("Invalid ad configuration: " + std::string(stripped));
+          continue;
+        }
         std::string position = std::string(parts[1]);
         AdConfig config;
         config.slot = std::string(parts[2]);
@@ -114,11 +131,21 @@ class MyRootContext : public RootContext {
 
   const std::string& getGptLibraryUrl() const { return gpt_library_url_; }
   bool shouldInjectGpt() const { return inject_gpt_library_; }
+  size_t getMaxHtmlBytes() const { return max_html_bytes_; }
 
  private:
+  // Returns true if size is in "WxH" format with positive integer dimensions.
+  static bool isValidSize(absl::string_view size) {
+    std::vector<absl::string_view> dims = absl::StrSplit(size, 'x');
+    uint32_t width, height;
+    return dims.size() == 2 && absl::SimpleAtoi(dims[0], &width) &&
+           absl::SimpleAtoi(dims[1], &height) && width > 0 && height > 0;
+  }
+
   std::map<std::string, AdConfig> ad_configs_;
   std::string gpt_library_url_;
   bool inject_gpt_library_;
+  size_t max_html_bytes_;
 };
 
 class MyHttpContext : public Context {
@@ -140,6 +167,14 @@ class MyHttpContext : public Context {
                                         bool end_of_stream) override {
     auto content_type = getResponseHeader("Content-Type");
     if (content_type && content_type->view().find("text/html") != std::string_view::npos) {
+      // Skip rewriting bodies known to exceed the size limit.
+      auto content_length = getResponseHeader("Content-Length");
+      size_t length;
+      if (content_length &&
+          absl::SimpleAtoi(content_length->view(), &length) &&
+          length > root_->getMaxHtmlBytes()) {
+        return FilterHeadersStatus::Continue;
+      }
       should_insert_ads_ = true;
       removeResponseHeader("Content-Length");
     }
@@ -151,6 +186,13 @@ class MyHttpContext : public Context {
       return FilterDataStatus::Continue;
     }
 
+    // Stop buffering and pass the body through untouched once it grows past
+    // the size limit.
+    if (body_size > root_->getMaxHtmlBytes()) {
+      should_insert_ads_ = false;
+      return FilterDataStatus::Continue;
+    }
+
     // Buffer the body until the end of the stream to ensure we process the complete HTML.
     // Processing chunks individually might split HTML tags and break marker matching.
     if (!end_of_stream) {
@@ -13,11 +13,17 @@
 // limitations under the License.
 
 // [START serviceextensions_plugin_ad_insertion]
+use log::*;
 use proxy_wasm::traits::*;
 use proxy_wasm::types::*;
 use std::collections::HashMap;
 
+// Default limit on the size of HTML bodies that are rewritten. Larger bodies
+// are passed through untouched to bound memory usage.
+const MAX_HTML_BYTES: usize = 1024 * 1024;
+
 proxy_wasm::main! {{
+    proxy_wasm::set_log_level(LogLevel::Trace);
     proxy_wasm::set_root_context(|_| -> Box<dyn RootContext> {
         Box::new(MyRootContext::new())
     });
@@ -35,6 +41,7 @@ struct MyRootContext {
     ad_configs: HashMap<String, AdConfig>,
     gpt_library_url: String,
     inject_gpt_library: bool,
+    max_html_bytes: usize,
 }
 
 impl MyRootContext {
@@ -63,6 +70,7 @@ impl MyRootContext {
             ad_configs,
             gpt_library_url: "https://securepubads.g.doubleclick.net/tag/js/gpt.js".to_string(),
             inject_gpt_library: true,
+            max_html_bytes: MAX_HTML_BYTES,
         }
     }
 }
@@ -98,7 +106,19 @@ impl RootContext for MyRootContext {
                         "inject_gpt" if parts.len() >= 2 => {
                             self.inject_gpt_library = parts[1] == "true";
                         }
+                        "max_html_bytes" if parts.len() >= 2 => match parts[1].parse() {
+                            Ok(max_html_bytes) => self.max_html_bytes = max_html_bytes,
+                            Err(_) => {
+                                warn!("Invalid configuration line: {}", line);
+                                self.max_html_bytes = MAX_HTML_BYTES;
+                            }
+                        },
                         "ad" if parts.len() >= 6 => {
+                            // Markers must be non-empty and sizes must be "WxH".
+                            if parts[5].is_empty() || !is_valid_size(parts[3]) {
+                                warn!("Invalid ad configuration: {}", line);
+                                continue;
+                            }
                             let position = parts[1].to_string();
                             let config = AdConfig {
                                 slot: parts[2].to_string(),
@@ -109,7 +129,7 @@ impl RootContext for MyRootContext {
                             self.ad_configs.insert(position, config);
                         }
                         _ => {
-                            // Ignore unknown configurations
+                            warn!("Invalid configuration line: {}", line);
                         }
                     }
                 }
@@ -123,6 +143,7 @@ impl RootContext for MyRootContext {
             ad_configs: self.ad_configs.clone(),
             gpt_library_url: self.gpt_library_url.clone(),
             inject_gpt_library: self.inject_gpt_library,
+            max_html_bytes: self.max_html_bytes,
             should_insert_ads: false,
             is_ad_request: false,
         }))
@@ -133,10 +154,22 @@ impl RootContext for MyRootContext {
     }
 }
 
+// Returns true if size is in "WxH" format with positive integer dimensions.
+fn is_valid_size(size: &str) -> bool {
+    match size.split_once('x') {
+        Some((width, height)) => {
+            matches!(width.parse::<u32>(), Ok(w) if w > 0)
+                && matches!(height.parse::<u32>(), Ok(h) if h > 0)
+        }
+        None => false,
+    }
+}
+
 struct MyHttpContext {
     ad_configs: HashMap<String, AdConfig>,
     gpt_library_url: String,
     inject_gpt_library: bool,
+    max_html_bytes: usize,
     should_insert_ads: bool,
     is_ad_request: bool,
 }
@@ -262,6 +295,14 @@ impl HttpContext for MyHttpContext {
     fn on_http_response_headers(&mut self, _num_headers: usize, _end_of_stream: bool) -> Action {
         if let Some(content_type) = self.get_http_response_header("Content-Type") {
             if content_type.contains("text/html") {
+                // Skip rewriting bodies known to exceed the size limit.
+                if let Some(content_length) = self.get_http_response_header("Content-Length") {
+                    if let Ok(length) = content_length.parse::<usize>() {
+                        if length > self.max_html_bytes {
+                            return Action::Continue;
+                        }
+                    }
+                }
                 self.should_insert_ads = true;
                 self.set_http_response_header("Content-Length", None);
             }
@@ -269,16 +310,36 @@ impl HttpContext for MyHttpContext {
         Action::Continue
     }
 
-    fn on_http_response_body(&mut self, body_size: usize, _end_of_stream: bool) -> Action {
+    fn on_http_response_body(&mut self, body_size: usize, end_of_stream: bool) -> Action {
         if !self.should_insert_ads || self.is_ad_request {
             return Action::Continue;
         }
 
+        // Stop buffering and pass the body through untouched once it grows
+        // past the size limit.
+        if body_size > self.max_html_bytes {
+            self.should_insert_ads = false;
+            return Action::Continue;
+        }
+
+        // Buffer the body until the end of the stream to ensure we process the complete HTML.
+        // Processing chunks individually might split HTML tags and break marker matching,
+        // or split multi-byte UTF-8 characters across chunks.
+        if !end_of_stream {
+            return Action::Pause;
+        }
+
         // Process HTML body and inject GAM ads
         if let Some(body_bytes) = self.get_http_response_body(0, body_size) {
-            if let Ok(body_str) = std::str::from_utf8(&body_bytes) {
-                let modified_body = self.process_body_with_gam(body_str);
-                self.set_http_response_body(0, body_size, modified_body.as_bytes());
+            match std::str::from_utf8(&body_bytes) {
+                Ok(body_str) => {
+                    let modified_body = self.process_body_with_gam(body_str);
+                    self.set_http_response_body(0, body_size, modified_body.as_bytes());
+                }
+                Err(e) => {
+                    // Pass non-UTF-8 bodies through untouched.
+                    warn!("Skipping ad insertion for non UTF-8 body: {}", e);
+                }
             }
         }
 
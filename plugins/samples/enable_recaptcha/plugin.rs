@@ -19,6 +19,7 @@
 // reCAPTCHA documentaion or following all user guide instructions. Please
 // follow official reCAPTCHA documentation at
 // https://developers.google.com/recaptcha.
+use json_config::load_json_config;
 use log::*;
 use lol_html::html_content::ContentType;
 use lol_html::*;
@@ -30,6 +31,10 @@ use std::cell::RefCell;
 use std::error::Error;
 use std::rc::Rc;
 
+// Default limit on the size of HTML bodies that are rewritten. Larger bodies
+// are passed through untouched to bound memory usage.
+const MAX_HTML_BYTES: usize = 1024 * 1024;
+
 proxy_wasm::main! {{
     proxy_wasm::set_log_level(LogLevel::Trace);
     proxy_wasm::set_root_context(|_| -> Box<dyn RootContext> {
@@ -43,10 +48,35 @@ struct MyRootContext {
     recaptcha_config: Rc<RefCell<RecaptchaConfig>>,
 }
 
-#[derive(Deserialize, Debug, Default)]
+#[derive(Deserialize, Debug)]
 struct RecaptchaConfig {
     recaptcha_key_type: String,
     recaptcha_key_value: String,
+    // Number of body bytes fed to the HtmlRewriter at a time.
+    #[serde(default = "default_chunk_size")]
+    chunk_size: usize,
+    // Bodies larger than this are passed through without script injection.
+    #[serde(default = "default_max_html_bytes")]
+    max_html_bytes: usize,
+}
+
+impl Default for RecaptchaConfig {
+    fn default() -> Self {
+        RecaptchaConfig {
+            recaptcha_key_type: String::new(),
+            recaptcha_key_value: String::new(),
+            chunk_size: default_chunk_size(),
+            max_html_bytes: default_max_html_bytes(),
+        }
+    }
+}
+
+fn default_chunk_size() -> usize {
+    500
+}
+
+fn default_max_html_bytes() -> usize {
+    MAX_HTML_BYTES
 }
 
 impl Context for MyRootContext {}
@@ -57,8 +87,7 @@ impl RootContext for MyRootContext {
             // Config file contains JSON formatted recaptcha config.
             // Failure to read config as UTF-8 or parse as JSON will cause plugin
             // to panic and crash.
-            let config_lines = String::from_utf8(config).unwrap();
-            let recaptcha_config: RecaptchaConfig = serde_json::from_str(&config_lines).unwrap();
+            let recaptcha_config: RecaptchaConfig = load_json_config(Some(config)).unwrap();
             // Valid JSON, but invalid recaptcha_key_type will cause the plugin to crash.
             if recaptcha_config.recaptcha_key_type != "SESSION"
                 && recaptcha_config.recaptcha_key_type != "ACTION"
@@ -68,6 +97,9 @@ impl RootContext for MyRootContext {
                     recaptcha_config.recaptcha_key_type
                 )
             }
+            if recaptcha_config.chunk_size == 0 {
+                panic!("Invalid chunk_size found. Plugin crashed. chunk_size=0")
+            }
             self.recaptcha_config = Rc::new(RefCell::new(recaptcha_config));
         }
         return true;
@@ -123,21 +155,36 @@ struct MyHttpContext<'a> {
     rewriter: Option<HtmlRewriter<'a, MyOutputSink>>,
     // True when plugin has added script to <head>.
     completed_script_injection: Rc<RefCell<bool>>,
+    // CSP nonce read from the response headers, shared with the content
+    // handler so it can be added to the injected <script> tag.
+    script_nonce: Rc<RefCell<Option<String>>>,
+    chunk_size: usize,
+    max_html_bytes: usize,
+    // Total number of response body bytes seen so far.
+    body_bytes_seen: usize,
 }
 
 impl<'a> MyHttpContext<'a> {
     pub fn new(config: Rc<RefCell<RecaptchaConfig>>) -> MyHttpContext<'a> {
         let output = Rc::new(RefCell::new(Vec::new()));
         let completed_script_injection = Rc::new(RefCell::new(false));
+        let script_nonce = Rc::new(RefCell::new(None));
         let recaptcha_config = config;
+        let chunk_size = recaptcha_config.borrow().chunk_size;
+        let max_html_bytes = recaptcha_config.borrow().max_html_bytes;
         let element_content_handler = Self::create_element_content_handler(
             recaptcha_config.clone(),
             completed_script_injection.clone(),
+            script_nonce.clone(),
         );
 
         MyHttpContext {
             output: output.clone(),
             completed_script_injection: completed_script_injection.clone(),
+            script_nonce,
+            chunk_size,
+            max_html_bytes,
+            body_bytes_seen: 0,
             rewriter: Some(HtmlRewriter::new(
                 Settings {
                     element_content_handlers: element_content_handler,
@@ -153,6 +200,7 @@ impl<'a> MyHttpContext<'a> {
     fn create_element_content_handler(
         recaptcha_config: Rc<RefCell<RecaptchaConfig>>,
         completed_script_injection: Rc<RefCell<bool>>,
+        script_nonce: Rc<RefCell<Option<String>>>,
     ) -> Vec<(Cow<'a, Selector>, ElementContentHandlers<'a>)> {
         let key_type = (*recaptcha_config.borrow()).recaptcha_key_type.clone();
         let content_handler = match key_type.as_str() {
@@ -160,8 +208,8 @@ impl<'a> MyHttpContext<'a> {
                 let key_value = (*recaptcha_config.borrow()).recaptcha_key_value.clone();
                 vec![element!("head", move |el| {
                     el.prepend(
-                            format!("\n<script src=\"https://www.google.com/recaptcha/enterprise.js?render=&waf={}\" async defer></script>\n",
-                            key_value).as_str(),
+                            format!("\n<script src=\"https://www.google.com/recaptcha/enterprise.js?render=&waf={}\"{} async defer></script>\n",
+                            key_value, nonce_attribute(&script_nonce.borrow())).as_str(),
                             ContentType::Html,
                             );
                     *completed_script_injection.borrow_mut() = true;
@@ -172,8 +220,8 @@ impl<'a> MyHttpContext<'a> {
                 let key_value = (*recaptcha_config.borrow()).recaptcha_key_value.clone();
                 vec![element!("head", move |el| {
                     el.prepend(
-                            format!("\n<script src=\"https://www.google.com/recaptcha/enterprise.js?render={}\"></script>\n",
-                            key_value).as_str(),
+                            format!("\n<script src=\"https://www.google.com/recaptcha/enterprise.js?render={}\"{}></script>\n",
+                            key_value, nonce_attribute(&script_nonce.borrow())).as_str(),
                             ContentType::Html,
                             );
                     *completed_script_injection.borrow_mut() = true;
@@ -211,16 +259,87 @@ impl<'a> MyHttpContext<'a> {
     }
 }
 
+// Returns the nonce attribute for the injected <script> tag, or an empty string
+// if the response has no CSP nonce.
+fn nonce_attribute(nonce: &Option<String>) -> String {
+    match nonce {
+        Some(nonce) => format!(" nonce=\"{}\"", nonce),
+        None => String::new(),
+    }
+}
+
+// Extracts the nonce from a Content-Security-Policy header value. The
+// script-src directive is used if present, falling back to default-src as
+// browsers do.
+fn extract_csp_nonce(csp: &str) -> Option<String> {
+    let find_directive = |name: &str| -> Option<Vec<&str>> {
+        csp.split(';')
+            .map(|directive| directive.split_whitespace().collect::<Vec<&str>>())
+            .find(|tokens| tokens.first().is_some_and(|t| t.eq_ignore_ascii_case(name)))
+    };
+    let sources = find_directive("script-src").or_else(|| find_directive("default-src"))?;
+    sources
+        .iter()
+        .skip(1)
+        .filter_map(|source| source.strip_prefix("'nonce-")?.strip_suffix('\''))
+        // Nonces are base64 values; reject anything that could break out of
+        // the attribute.
+        .find(|nonce| {
+            !nonce.is_empty()
+                && nonce
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+/=_-".contains(c))
+        })
+        .map(|nonce| nonce.to_string())
+}
+
 impl<'a> Context for MyHttpContext<'a> {}
 
 impl<'a> HttpContext for MyHttpContext<'a> {
+    fn on_http_response_headers(&mut self, _: usize, _: bool) -> Action {
+        if let Some(csp) = self.get_http_response_header("Content-Security-Policy") {
+            *self.script_nonce.borrow_mut() = extract_csp_nonce(&csp);
+        }
+        // Skip rewriting bodies known to exceed the size limit.
+        if let Some(content_length) = self.get_http_response_header("Content-Length") {
+            if let Ok(length) = content_length.parse::<usize>() {
+                if length > self.max_html_bytes {
+                    *self.completed_script_injection.borrow_mut() = true;
+                }
+            }
+        }
+        return Action::Continue;
+    }
+
     fn on_http_response_body(&mut self, body_size: usize, _: bool) -> Action {
-        let chunk_size = 500;
+        let chunk_size = self.chunk_size;
         if *self.completed_script_injection.borrow() {
             // Return immediately if plugin is "done" to avoid unnecessary work
             // and resource usage.
             return Action::Continue;
         }
+        self.body_bytes_seen += body_size;
+        if self.body_bytes_seen > self.max_html_bytes {
+            // Give up on script injection once the body grows past the size
+            // limit. Flush whatever the rewriter is holding from previous
+            // chunks and pass the current chunk through untouched.
+            warn!(
+                "Response body exceeds {} bytes, skipping reCAPTCHA injection",
+                self.max_html_bytes
+            );
+            *self.completed_script_injection.borrow_mut() = true;
+            if let Err(e) = self.end_rewriter() {
+                error!("Error while ending HtmlRewriter: {}", e);
+                return Action::Continue;
+            }
+            if let Some(body_bytes) = self.get_http_response_body(0, body_size) {
+                self.output.borrow_mut().extend_from_slice(&body_bytes);
+            }
+            self.set_http_response_body(0, body_size, self.output.borrow().as_slice());
+            // Clear output after usage to avoid unnecessary memory growth.
+            self.output.borrow_mut().clear();
+            return Action::Continue;
+        }
         for start_index in (0..body_size).step_by(chunk_size) {
             if let Some(body_bytes) = self.get_http_response_body(start_index, chunk_size) {
                 if let Err(e) = self.parse_chunk(body_bytes) {
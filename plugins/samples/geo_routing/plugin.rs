@@ -0,0 +1,161 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// [START serviceextensions_plugin_geo_routing_rules]
+use json_config::load_json_config;
+use log::*;
+use proxy_wasm::traits::*;
+use proxy_wasm::types::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+proxy_wasm::main! {{
+    proxy_wasm::set_log_level(LogLevel::Trace);
+    proxy_wasm::set_root_context(|_| -> Box<dyn RootContext> {
+        Box::new(MyRootContext {
+            config: Rc::new(GeoRoutingConfig::default()),
+        })
+    });
+}}
+
+fn default_region_header() -> String {
+    "x-client-region".to_string()
+}
+
+fn default_routing_header() -> String {
+    "x-backend".to_string()
+}
+
+#[derive(Deserialize, Debug)]
+struct GeoRoutingConfig {
+    // Request header carrying the client region, set by the load balancer.
+    #[serde(default = "default_region_header")]
+    region_header: String,
+    // Request header used to pass the selected backend upstream.
+    #[serde(default = "default_routing_header")]
+    routing_header: String,
+    // Region to backend rules. Regions are matched case-insensitively.
+    rules: HashMap<String, String>,
+    // Region whose backend is used when the client region has no rule.
+    #[serde(default)]
+    default_region: Option<String>,
+    // Also rewrite :authority to the selected backend.
+    #[serde(default)]
+    rewrite_authority: bool,
+}
+
+impl Default for GeoRoutingConfig {
+    fn default() -> Self {
+        GeoRoutingConfig {
+            region_header: default_region_header(),
+            routing_header: default_routing_header(),
+            rules: HashMap::new(),
+            default_region: None,
+            rewrite_authority: false,
+        }
+    }
+}
+
+impl GeoRoutingConfig {
+    fn backend_for(&self, region: Option<&str>) -> Option<&String> {
+        region
+            .and_then(|r| self.rules.get(r))
+            .or_else(|| self.default_region.as_ref().and_then(|d| self.rules.get(d)))
+    }
+}
+
+struct MyRootContext {
+    config: Rc<GeoRoutingConfig>,
+}
+
+impl Context for MyRootContext {}
+
+impl RootContext for MyRootContext {
+    fn on_configure(&mut self, _: usize) -> bool {
+        // Config file contains JSON formatted routing rules.
+        let mut config = match load_json_config::<GeoRoutingConfig>(self.get_plugin_configuration())
+        {
+            Ok(config) => config,
+            Err(e) => {
+                error!("{}", e);
+                return false;
+            }
+        };
+        if config.rules.is_empty() {
+            error!("At least one region rule must be configured");
+            return false;
+        }
+        config.rules = config
+            .rules
+            .into_iter()
+            .map(|(region, backend)| (region.to_ascii_lowercase(), backend))
+            .collect();
+        if let Some(region) = config.default_region.take() {
+            let region = region.to_ascii_lowercase();
+            if !config.rules.contains_key(&region) {
+                error!("Default region {} has no rule", region);
+                return false;
+            }
+            config.default_region = Some(region);
+        }
+        info!("Loaded {} region rules", config.rules.len());
+        self.config = Rc::new(config);
+        return true;
+    }
+
+    fn create_http_context(&self, _: u32) -> Option<Box<dyn HttpContext>> {
+        Some(Box::new(MyHttpContext {
+            config: self.config.clone(),
+        }))
+    }
+
+    fn get_type(&self) -> Option<ContextType> {
+        Some(ContextType::HttpContext)
+    }
+}
+
+struct MyHttpContext {
+    config: Rc<GeoRoutingConfig>,
+}
+
+impl Context for MyHttpContext {}
+
+impl HttpContext for MyHttpContext {
+    fn on_http_request_headers(&mut self, _: usize, _: bool) -> Action {
+        let region = self
+            .get_http_request_header(&self.config.region_header)
+            .map(|r| r.trim().to_ascii_lowercase())
+            .filter(|r| !r.is_empty());
+        let config = self.config.clone();
+        match config.backend_for(region.as_deref()) {
+            Some(backend) => {
+                debug!("Routing region {:?} to {}", region, backend);
+                // Overwrites any client-supplied value so routing can trust it.
+                self.set_http_request_header(&config.routing_header, Some(backend));
+                if config.rewrite_authority {
+                    // Envoy clears the route cache when a Wasm plugin modifies
+                    // request headers, so the new authority is used for routing.
+                    self.set_http_request_header(":authority", Some(backend));
+                }
+            }
+            None => {
+                debug!("No backend for region {:?}", region);
+                self.set_http_request_header(&config.routing_header, None);
+            }
+        }
+        return Action::Continue;
+    }
+}
+// [END serviceextensions_plugin_geo_routing_rules]
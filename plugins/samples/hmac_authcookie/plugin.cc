@@ -14,9 +14,11 @@
 
 // [START serviceextensions_plugin_hmac_authcookie]
 #include <openssl/hmac.h>
+#include <openssl/mem.h>
 
 #include <string>
 
+#include "absl/strings/ascii.h"
 #include "absl/strings/escaping.h"
 #include "absl/strings/str_split.h"
 #include "absl/time/clock.h"
@@ -24,21 +26,92 @@
 #include "proxy_wasm_intrinsics.h"
 #include "re2/re2.h"
 
-// Replace with your desired secret key.
-const std::string kSecretKey = "your_secret_key";
+// Default secret key, used when the plugin configuration does not provide
+// one. Replace with your desired secret key or set "secret_key" in the plugin
+// configuration.
+const std::string kDefaultSecretKey = "your_secret_key";
+// Default header to read the client IP from.
+const std::string kDefaultClientIpHeader = "X-Forwarded-For";
 
 class MyRootContext : public RootContext {
  public:
   explicit MyRootContext(uint32_t id, std::string_view root_id)
       : RootContext(id, root_id) {}
 
-  bool onConfigure(size_t) override {
+  bool onConfigure(size_t config_len) override {
+    secret_key = kDefaultSecretKey;
+    client_ip_header = kDefaultClientIpHeader;
+    bool allow_ipv6 = false;
+
+    // Configuration is optional. Expected format per line (space-separated):
+    // secret_key <secret>
+    // client_ip_header <header name>
+    // allow_ipv6 <true|false>
+    if (config_len > 0) {
+      auto config_data =
+          getBufferBytes(WasmBufferType::PluginConfiguration, 0, config_len);
+      if (!config_data) {
+        LOG_ERROR("Failed to read plugin configuration");
+        return false;
+      }
+      for (absl::string_view line :
+           absl::StrSplit(config_data->view(), '\n')) {
+        absl::string_view stripped = absl::StripAsciiWhitespace(line);
+        // Skip empty lines or comments
+        if (stripped.empty() || stripped[0] == '#') continue;
+
+        std::pair<std::string, std::string> key_value =
+            absl::StrSplit(stripped, absl::MaxSplits(' ', 1));
+        if (key_value.first == "secret_key" && !key_value.second.empty()) {
+          secret_key = key_value.second;
+        } else if (key_value.first == "client_ip_header" &&
+                   !key_value.second.empty()) {
+          client_ip_header = key_value.second;
+        } else if (key_value.first == "allow_ipv6") {
+          allow_ipv6 = key_value.second == "true";
+        } else {
+          LOG_WARN("Invalid configuration line: " + std::string(stripped));
+        }
+      }
+    }
+
     // Regex for matching IPs on format like 127.0.0.1.
     ip_match.emplace("^(?:[0-9]{1,3}\\.){3}[0-9]{1,3}$");
-    return ip_match->ok();
+    if (!ip_match->ok()) return false;
+    if (allow_ipv6) {
+      // Regex for matching IPs on format like 2001:db8::1 or ::ffff:1.2.3.4.
+      // Each alternative allows at most one "::" and requires at least one
+      // hex group (or an embedded IPv4 address).
+      ipv6_match.emplace(
+          "^(?:"
+          // 1:2:3:4:5:6:7:8
+          "(?:[0-9a-fA-F]{1,4}:){7}[0-9a-fA-F]{1,4}|"
+          // 1::, 1:2:3:4:5:6:7::
+          "(?:[0-9a-fA-F]{1,4}:){1,7}:|"
+          // 1::8, 1:2:3:4:5:6::8
+          "(?:[0-9a-fA-F]{1,4}:){1,6}:[0-9a-fA-F]{1,4}|"
+          "(?:[0-9a-fA-F]{1,4}:){1,5}(?::[0-9a-fA-F]{1,4}){1,2}|"
+          "(?:[0-9a-fA-F]{1,4}:){1,4}(?::[0-9a-fA-F]{1,4}){1,3}|"
+          "(?:[0-9a-fA-F]{1,4}:){1,3}(?::[0-9a-fA-F]{1,4}){1,4}|"
+          "(?:[0-9a-fA-F]{1,4}:){1,2}(?::[0-9a-fA-F]{1,4}){1,5}|"
+          "[0-9a-fA-F]{1,4}:(?::[0-9a-fA-F]{1,4}){1,6}|"
+          // ::8, ::2:3:4:5:6:7:8
+          ":(?::[0-9a-fA-F]{1,4}){1,7}|"
+          // ::1.2.3.4, ::ffff:1.2.3.4, ::ffff:0:1.2.3.4
+          "::(?:ffff(?::0{1,4})?:)?(?:[0-9]{1,3}\\.){3}[0-9]{1,3}|"
+          // 64:ff9b::1.2.3.4
+          "(?:[0-9a-fA-F]{1,4}:){1,4}:(?:[0-9]{1,3}\\.){3}[0-9]{1,3}"
+          ")$");
+      if (!ipv6_match->ok()) return false;
+    }
+    return true;
   }
 
+  std::string secret_key;
+  std::string client_ip_header;
   std::optional<re2::RE2> ip_match;
+  // Only set when IPv6 client addresses are allowed.
+  std::optional<re2::RE2> ipv6_match;
 };
 
 // Validates the HMAC HTTP cookie performing the following steps:
@@ -82,8 +155,8 @@ class MyHttpContext : public Context {
       return FilterHeadersStatus::ContinueAndEndStream;
     }
 
-    if (computeHmacSignature(payload_and_hash->first) !=
-        payload_and_hash->second) {
+    if (!isHmacSignatureValid(payload_and_hash->first,
+                              payload_and_hash->second)) {
       LOG_INFO("Access forbidden - invalid HMAC hash.");
       sendLocalResponse(403, "", "Access forbidden - invalid HMAC hash.\n", {});
       return FilterHeadersStatus::ContinueAndEndStream;
@@ -119,11 +192,16 @@ class MyHttpContext : public Context {
            unix_now <= parsed_expiration_timestamp;
   }
 
-  // Try to get the client IP from the X-Forwarded-For header.
+  // Try to get the client IP from the configured header (X-Forwarded-For by
+  // default).
   std::optional<std::string> getClientIp() {
-    const std::string ips = getRequestHeader("X-Forwarded-For")->toString();
+    const std::string ips =
+        getRequestHeader(root_->client_ip_header)->toString();
     for (absl::string_view ip : absl::StrSplit(ips, ',')) {
-      if (re2::RE2::FullMatch(ip, *root_->ip_match)) {
+      ip = absl::StripAsciiWhitespace(ip);
+      if (re2::RE2::FullMatch(ip, *root_->ip_match) ||
+          (root_->ipv6_match.has_value() &&
+           re2::RE2::FullMatch(ip, *root_->ipv6_match))) {
         return std::string(ip);
       }
     }
@@ -147,7 +225,7 @@ class MyHttpContext : public Context {
   }
 
   // Try to parse the authorization cookie in the format
-  // "base64(payload)" + "." + "base64(HMAC(payload))".
+  // "base64(payload)" + "." + "base64(hex(HMAC(payload)))".
   std::optional<std::pair<std::string, std::string>> parseAuthorizationCookie(
       std::string_view cookie) {
     std::pair<std::string_view, std::string_view> payload_and_hash =
@@ -162,11 +240,22 @@ class MyHttpContext : public Context {
     return std::nullopt;
   }
 
-  // Function to compute the HMAC signature.
+  // Compares the expected signature against the one provided in the cookie
+  // in constant time, so that response timing does not reveal how many
+  // leading characters of a forged signature are correct.
+  bool isHmacSignatureValid(std::string_view data,
+                            std::string_view signature) {
+    const std::string expected = computeHmacSignature(data);
+    return expected.size() == signature.size() &&
+           CRYPTO_memcmp(expected.data(), signature.data(), expected.size()) ==
+               0;
+  }
+
+  // Function to compute the HMAC signature as a lowercase hex string.
   std::string computeHmacSignature(std::string_view data) {
     unsigned char result[EVP_MAX_MD_SIZE];
     unsigned int len;
-    HMAC(EVP_sha256(), kSecretKey.c_str(), kSecretKey.length(),
+    HMAC(EVP_sha256(), root_->secret_key.c_str(), root_->secret_key.length(),
          reinterpret_cast<const unsigned char*>(std::string{data}.c_str()),
          data.length(), result, &len);
     return absl::BytesToHexString(std::string(result, result + len));
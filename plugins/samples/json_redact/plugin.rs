@@ -0,0 +1,167 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// [START serviceextensions_plugin_json_redact]
+use json_config::load_json_config;
+use log::*;
+use proxy_wasm::traits::*;
+use proxy_wasm::types::*;
+use serde::Deserialize;
+use serde_json::Value;
+use std::rc::Rc;
+
+const REDACTED: &str = "[REDACTED]";
+
+proxy_wasm::main! {{
+    proxy_wasm::set_log_level(LogLevel::Trace);
+    proxy_wasm::set_root_context(|_| -> Box<dyn RootContext> {
+        Box::new(MyRootContext {
+            fields: Rc::new(Vec::new()),
+        })
+    });
+}}
+
+#[derive(Deserialize, Debug, Default)]
+struct RedactConfig {
+    // Dotted paths of the fields to redact, e.g. "user.ssn".
+    #[serde(default)]
+    fields: Vec<String>,
+}
+
+struct MyRootContext {
+    // Each field path pre-split into its components.
+    fields: Rc<Vec<Vec<String>>>,
+}
+
+impl Context for MyRootContext {}
+
+impl RootContext for MyRootContext {
+    fn on_configure(&mut self, _: usize) -> bool {
+        // Config file contains JSON formatted list of fields to redact.
+        let config = match load_json_config::<RedactConfig>(self.get_plugin_configuration()) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("{}", e);
+                return false;
+            }
+        };
+        let mut fields = Vec::new();
+        for field in config.fields {
+            let path: Vec<String> = field.split('.').map(String::from).collect();
+            if path.iter().any(|part| part.is_empty()) {
+                error!("Invalid field path: {}", field);
+                return false;
+            }
+            fields.push(path);
+        }
+        info!("Redacting {} fields", fields.len());
+        self.fields = Rc::new(fields);
+        return true;
+    }
+
+    fn create_http_context(&self, _: u32) -> Option<Box<dyn HttpContext>> {
+        Some(Box::new(MyHttpContext {
+            fields: self.fields.clone(),
+            is_json: false,
+        }))
+    }
+
+    fn get_type(&self) -> Option<ContextType> {
+        Some(ContextType::HttpContext)
+    }
+}
+
+// Replaces the value at `path` with REDACTED. Arrays are traversed so that a
+// path applies to every element. Returns true if anything was redacted.
+fn redact(value: &mut Value, path: &[String]) -> bool {
+    match value {
+        Value::Array(items) => {
+            let mut redacted = false;
+            for item in items.iter_mut() {
+                redacted |= redact(item, path);
+            }
+            redacted
+        }
+        Value::Object(map) => {
+            let (first, rest) = match path.split_first() {
+                Some(split) => split,
+                None => return false,
+            };
+            match map.get_mut(first) {
+                Some(child) if rest.is_empty() => {
+                    *child = Value::String(REDACTED.to_string());
+                    true
+                }
+                Some(child) => redact(child, rest),
+                None => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+struct MyHttpContext {
+    fields: Rc<Vec<Vec<String>>>,
+    is_json: bool,
+}
+
+impl Context for MyHttpContext {}
+
+impl HttpContext for MyHttpContext {
+    fn on_http_request_headers(&mut self, _: usize, _: bool) -> Action {
+        if self.fields.is_empty() {
+            return Action::Continue;
+        }
+        if let Some(content_type) = self.get_http_request_header("Content-Type") {
+            self.is_json = content_type.contains("application/json");
+        }
+        if self.is_json {
+            // The redacted body may differ in length from the original.
+            self.set_http_request_header("Content-Length", None);
+        }
+        return Action::Continue;
+    }
+
+    fn on_http_request_body(&mut self, body_size: usize, end_of_stream: bool) -> Action {
+        if !self.is_json {
+            return Action::Continue;
+        }
+        // Buffer the body until the end of the stream so that the whole
+        // document can be parsed at once.
+        if !end_of_stream {
+            return Action::Pause;
+        }
+        let body = match self.get_http_request_body(0, body_size) {
+            Some(body) => body,
+            None => return Action::Continue,
+        };
+        let mut document = match serde_json::from_slice::<Value>(&body) {
+            Ok(document) => document,
+            Err(e) => {
+                warn!("Request body is not valid JSON, passing through: {}", e);
+                return Action::Continue;
+            }
+        };
+        let mut redacted = false;
+        for path in self.fields.iter() {
+            redacted |= redact(&mut document, path);
+        }
+        // Leave the original bytes untouched if nothing was redacted.
+        if redacted {
+            self.set_http_request_body(0, body_size, document.to_string().as_bytes());
+        }
+        return Action::Continue;
+    }
+}
+// [END serviceextensions_plugin_json_redact]
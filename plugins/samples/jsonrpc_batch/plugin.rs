@@ -0,0 +1,159 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// [START serviceextensions_plugin_jsonrpc_batch]
+use json_config::load_json_config;
+use log::*;
+use proxy_wasm::traits::*;
+use proxy_wasm::types::*;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::rc::Rc;
+
+// JSON-RPC 2.0 "Invalid Request" error code.
+const INVALID_REQUEST: i64 = -32600;
+
+proxy_wasm::main! {{
+    proxy_wasm::set_log_level(LogLevel::Trace);
+    proxy_wasm::set_root_context(|_| -> Box<dyn RootContext> {
+        Box::new(MyRootContext {
+            config: Rc::new(BatchConfig::default()),
+        })
+    });
+}}
+
+#[derive(Deserialize, Debug, Default)]
+struct BatchConfig {
+    // Maximum number of calls allowed in a single batch. 0 means unlimited.
+    #[serde(default)]
+    max_batch_size: usize,
+}
+
+struct MyRootContext {
+    config: Rc<BatchConfig>,
+}
+
+impl Context for MyRootContext {}
+
+impl RootContext for MyRootContext {
+    fn on_configure(&mut self, _: usize) -> bool {
+        // Config file contains optional JSON formatted batch config.
+        match load_json_config::<BatchConfig>(self.get_plugin_configuration()) {
+            Ok(config) => self.config = Rc::new(config),
+            Err(e) => {
+                error!("{}", e);
+                return false;
+            }
+        }
+        info!("Max batch size {0}", self.config.max_batch_size);
+        return true;
+    }
+
+    fn create_http_context(&self, _: u32) -> Option<Box<dyn HttpContext>> {
+        Some(Box::new(MyHttpContext {
+            config: self.config.clone(),
+            is_json: false,
+        }))
+    }
+
+    fn get_type(&self) -> Option<ContextType> {
+        Some(ContextType::HttpContext)
+    }
+}
+
+struct MyHttpContext {
+    config: Rc<BatchConfig>,
+    is_json: bool,
+}
+
+impl MyHttpContext {
+    // Returns an error message if the batch is not acceptable.
+    fn validate_batch(&self, batch: &[Value]) -> Result<(), String> {
+        if batch.is_empty() {
+            return Err("Empty batch".to_string());
+        }
+        if self.config.max_batch_size > 0 && batch.len() > self.config.max_batch_size {
+            return Err(format!(
+                "Batch size {} exceeds limit of {}",
+                batch.len(),
+                self.config.max_batch_size
+            ));
+        }
+        for (index, member) in batch.iter().enumerate() {
+            if member.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+                return Err(format!(
+                    "Batch member {} has invalid jsonrpc version",
+                    index
+                ));
+            }
+            if !member.get("method").is_some_and(Value::is_string) {
+                return Err(format!("Batch member {} is missing method", index));
+            }
+        }
+        Ok(())
+    }
+
+    fn send_jsonrpc_error(&self, message: &str) {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "error": { "code": INVALID_REQUEST, "message": message },
+            "id": null,
+        })
+        .to_string();
+        self.send_http_response(
+            400,
+            vec![("Content-Type", "application/json")],
+            Some(body.as_bytes()),
+        );
+    }
+}
+
+impl Context for MyHttpContext {}
+
+impl HttpContext for MyHttpContext {
+    fn on_http_request_headers(&mut self, _: usize, _: bool) -> Action {
+        if let Some(content_type) = self.get_http_request_header("Content-Type") {
+            self.is_json = content_type.contains("application/json");
+        }
+        return Action::Continue;
+    }
+
+    fn on_http_request_body(&mut self, body_size: usize, end_of_stream: bool) -> Action {
+        if !self.is_json {
+            return Action::Continue;
+        }
+        // Buffer the body until the end of the stream so that the whole batch
+        // can be parsed at once.
+        if !end_of_stream {
+            return Action::Pause;
+        }
+        let body = match self.get_http_request_body(0, body_size) {
+            Some(body) => body,
+            None => return Action::Continue,
+        };
+        // Single (non-batch) requests and non-JSON bodies are passed through
+        // untouched and left for the upstream to validate.
+        let batch = match serde_json::from_slice::<Value>(&body) {
+            Ok(Value::Array(batch)) => batch,
+            _ => return Action::Continue,
+        };
+        if let Err(message) = self.validate_batch(&batch) {
+            warn!("Rejecting JSON-RPC batch: {}", message);
+            self.send_jsonrpc_error(&message);
+            return Action::Pause;
+        }
+        return Action::Continue;
+    }
+}
+// [END serviceextensions_plugin_jsonrpc_batch]
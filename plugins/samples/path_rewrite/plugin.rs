@@ -0,0 +1,124 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// [START serviceextensions_plugin_path_rewrite]
+use json_config::load_json_config;
+use log::*;
+use proxy_wasm::traits::*;
+use proxy_wasm::types::*;
+use regex::Regex;
+use serde::Deserialize;
+use std::rc::Rc;
+
+proxy_wasm::main! {{
+    proxy_wasm::set_log_level(LogLevel::Trace);
+    proxy_wasm::set_root_context(|_| -> Box<dyn RootContext> {
+        Box::new(MyRootContext {
+            rules: Rc::new(Vec::new()),
+        })
+    });
+}}
+
+#[derive(Deserialize, Debug, Default)]
+struct RewriteConfig {
+    #[serde(default)]
+    rules: Vec<RuleConfig>,
+}
+
+#[derive(Deserialize, Debug)]
+struct RuleConfig {
+    pattern: String,
+    replacement: String,
+}
+
+struct RewriteRule {
+    pattern: Regex,
+    replacement: String,
+}
+
+struct MyRootContext {
+    rules: Rc<Vec<RewriteRule>>,
+}
+
+impl Context for MyRootContext {}
+
+impl RootContext for MyRootContext {
+    fn on_configure(&mut self, _: usize) -> bool {
+        // Config file contains JSON formatted rewrite rules.
+        let config = match load_json_config::<RewriteConfig>(self.get_plugin_configuration()) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("{}", e);
+                return false;
+            }
+        };
+        let mut rules = Vec::new();
+        for rule in config.rules {
+            match Regex::new(&rule.pattern) {
+                Ok(pattern) => rules.push(RewriteRule {
+                    pattern,
+                    replacement: rule.replacement,
+                }),
+                Err(e) => {
+                    error!("Invalid rewrite pattern {}: {}", rule.pattern, e);
+                    return false;
+                }
+            }
+        }
+        if rules.is_empty() {
+            warn!("No rewrite rules configured, paths will not be rewritten");
+        }
+        info!("Loaded {} rewrite rules", rules.len());
+        self.rules = Rc::new(rules);
+        return true;
+    }
+
+    fn create_http_context(&self, _: u32) -> Option<Box<dyn HttpContext>> {
+        Some(Box::new(MyHttpContext {
+            rules: self.rules.clone(), // shallow copy, ref count only
+        }))
+    }
+
+    fn get_type(&self) -> Option<ContextType> {
+        Some(ContextType::HttpContext)
+    }
+}
+
+struct MyHttpContext {
+    rules: Rc<Vec<RewriteRule>>,
+}
+
+impl Context for MyHttpContext {}
+
+impl HttpContext for MyHttpContext {
+    fn on_http_request_headers(&mut self, _: usize, _: bool) -> Action {
+        if let Some(path) = self.get_http_request_header(":path") {
+            // Rules only see the path component; the query string (if any) is
+            // carried over unchanged.
+            let (path_only, query) = match path.find('?') {
+                Some(pos) => path.split_at(pos),
+                None => (path.as_str(), ""),
+            };
+            // Apply the first matching rule only.
+            if let Some(rule) = self.rules.iter().find(|r| r.pattern.is_match(path_only)) {
+                let edit = rule.pattern.replace(path_only, rule.replacement.as_str());
+                let new_path = format!("{}{}", edit, query);
+                info!("Rewriting path {} to {}", path, new_path);
+                self.set_http_request_header(":path", Some(&new_path));
+            }
+        }
+        return Action::Continue;
+    }
+}
+// [END serviceextensions_plugin_path_rewrite]
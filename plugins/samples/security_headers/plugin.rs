@@ -0,0 +1,118 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// [START serviceextensions_plugin_security_headers]
+use json_config::load_json_config;
+use log::*;
+use proxy_wasm::traits::*;
+use proxy_wasm::types::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+// Headers injected when no configuration is provided. Content-Security-Policy
+// is application specific, so it is only added when configured.
+const DEFAULT_HEADERS: &[(&str, &str)] = &[
+    ("referrer-policy", "strict-origin-when-cross-origin"),
+    (
+        "strict-transport-security",
+        "max-age=31536000; includeSubDomains",
+    ),
+    ("x-content-type-options", "nosniff"),
+];
+
+proxy_wasm::main! {{
+    proxy_wasm::set_log_level(LogLevel::Trace);
+    proxy_wasm::set_root_context(|_| -> Box<dyn RootContext> {
+        Box::new(MyRootContext {
+            headers: Rc::new(Vec::new()),
+        })
+    });
+}}
+
+#[derive(Deserialize, Debug, Default)]
+struct SecurityHeadersConfig {
+    // Header name to value. Replaces the default set when present.
+    headers: Option<BTreeMap<String, String>>,
+}
+
+struct MyRootContext {
+    headers: Rc<Vec<(String, String)>>,
+}
+
+impl Context for MyRootContext {}
+
+impl RootContext for MyRootContext {
+    fn on_configure(&mut self, _: usize) -> bool {
+        // Config file contains optional JSON formatted header set.
+        let config =
+            match load_json_config::<SecurityHeadersConfig>(self.get_plugin_configuration()) {
+                Ok(config) => config,
+                Err(e) => {
+                    error!("{}", e);
+                    return false;
+                }
+            };
+        let headers: Vec<(String, String)> = match config.headers {
+            Some(headers) => headers
+                .into_iter()
+                .map(|(name, value)| (name.to_ascii_lowercase(), value))
+                .collect(),
+            None => DEFAULT_HEADERS
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        };
+        for (name, value) in headers.iter() {
+            if name.is_empty() || value.is_empty() {
+                error!("Header name and value must not be empty: {:?}", name);
+                return false;
+            }
+        }
+        info!("Injecting {} security headers", headers.len());
+        self.headers = Rc::new(headers);
+        return true;
+    }
+
+    fn create_http_context(&self, _: u32) -> Option<Box<dyn HttpContext>> {
+        Some(Box::new(MyHttpContext {
+            headers: self.headers.clone(),
+        }))
+    }
+
+    fn get_type(&self) -> Option<ContextType> {
+        Some(ContextType::HttpContext)
+    }
+}
+
+struct MyHttpContext {
+    headers: Rc<Vec<(String, String)>>,
+}
+
+impl Context for MyHttpContext {}
+
+impl HttpContext for MyHttpContext {
+    fn on_http_response_headers(&mut self, _: usize, _: bool) -> Action {
+        for (name, value) in self.headers.iter() {
+            // Values set by the origin take precedence.
+            if self.get_http_response_header(name).is_some() {
+                debug!("Origin already set {}, skipping", name);
+                continue;
+            }
+            self.add_http_response_header(name, value);
+        }
+        return Action::Continue;
+    }
+}
+// [END serviceextensions_plugin_security_headers]
@@ -0,0 +1,96 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helper for plugins that read a JSON document from their plugin
+//! configuration, so that every sample reports UTF-8 and JSON errors the same
+//! way and treats a missing configuration consistently.
+
+use serde::de::DeserializeOwned;
+
+/// Parses raw plugin configuration (as returned by
+/// `get_plugin_configuration()`) as JSON.
+///
+/// A missing or empty configuration yields `T::default()`. Invalid UTF-8 or
+/// JSON yields an error message suitable for logging.
+pub fn load_json_config<T: DeserializeOwned + Default>(raw: Option<Vec<u8>>) -> Result<T, String> {
+    let bytes = match raw {
+        Some(bytes) if !bytes.is_empty() => bytes,
+        _ => return Ok(T::default()),
+    };
+    let config = String::from_utf8(bytes)
+        .map_err(|e| format!("Plugin configuration is not valid UTF-8: {}", e))?;
+    serde_json::from_str(&config)
+        .map_err(|e| format!("Failed to parse plugin configuration as JSON: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug, Default, PartialEq)]
+    struct TestConfig {
+        name: String,
+        #[serde(default)]
+        limit: usize,
+    }
+
+    #[test]
+    fn missing_config_uses_default() {
+        assert_eq!(
+            load_json_config::<TestConfig>(None),
+            Ok(TestConfig::default())
+        );
+    }
+
+    #[test]
+    fn empty_config_uses_default() {
+        assert_eq!(
+            load_json_config::<TestConfig>(Some(Vec::new())),
+            Ok(TestConfig::default())
+        );
+    }
+
+    #[test]
+    fn invalid_utf8_is_rejected() {
+        let err = load_json_config::<TestConfig>(Some(vec![b'{', 0xff, b'}'])).unwrap_err();
+        assert!(
+            err.starts_with("Plugin configuration is not valid UTF-8"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn invalid_json_is_rejected() {
+        let err = load_json_config::<TestConfig>(Some(b"{\"name\":".to_vec())).unwrap_err();
+        assert!(
+            err.starts_with("Failed to parse plugin configuration as JSON"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn valid_json_is_parsed() {
+        let config = load_json_config::<TestConfig>(Some(b"{\"name\": \"test\"}".to_vec()));
+        assert_eq!(
+            config,
+            Ok(TestConfig {
+                name: "test".to_string(),
+                limit: 0,
+            })
+        );
+    }
+}